@@ -0,0 +1,255 @@
+//! Alternate front-end that builds [`FileDomain`]s straight from the ABI JSON emitted by
+//! `scarb build` / `starknet-class`, mirroring how ethers-rs' `Abigen` builds bindings from an
+//! ABI instead of from source. This is robust to formatting, comments and generics, and
+//! correctly flattens Cairo 2 event enums that `parser::parse_cairo_file` cannot recognize.
+
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use thiserror::Error;
+
+use crate::parser::{CairoArgument, CairoEvent, FileDomain, PostgresType};
+
+/// Nested struct members are flattened recursively; this bounds the recursion so a
+/// self-referential ABI (or a cycle across a few types) can't blow the stack.
+const MAX_RESOLUTION_DEPTH: usize = 8;
+
+#[derive(Debug, Error)]
+pub enum AbiError {
+    #[error("failed to read ABI file {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to parse ABI JSON: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum AbiEntry {
+    Event(AbiEvent),
+    Struct(AbiStruct),
+    // We don't resolve enum-valued members against their declared variants (see the comment in
+    // `resolve_member`), so there's nothing to extract from a top-level `enum` entry either.
+    Enum(IgnoredAny),
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct AbiEvent {
+    name: String,
+    kind: AbiEventKind,
+    #[serde(default)]
+    members: Vec<AbiMember>,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum AbiEventKind {
+    Struct,
+    Enum,
+}
+
+#[derive(Debug, Deserialize)]
+struct AbiStruct {
+    name: String,
+    #[serde(default)]
+    members: Vec<AbiMember>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AbiMember {
+    name: String,
+    r#type: String,
+}
+
+/// Parses a compiled Starknet class ABI JSON file into a single [`FileDomain`], resolving event
+/// members against the `struct` entries declared alongside them.
+pub(crate) fn parse_abi_file<P: AsRef<Path> + std::convert::AsRef<std::ffi::OsStr>>(
+    file: P,
+) -> Result<FileDomain, AbiError> {
+    let content = std::fs::read_to_string(&file)?;
+    let entries: Vec<AbiEntry> = serde_json::from_str(&content)?;
+    let mut file_domain = FileDomain::new_from_abi_path(&file);
+
+    let mut structs: HashMap<&str, &AbiStruct> = HashMap::new();
+    let mut events: Vec<&AbiEvent> = Vec::new();
+
+    for entry in &entries {
+        match entry {
+            AbiEntry::Struct(s) => {
+                structs.insert(s.name.as_str(), s);
+            }
+            AbiEntry::Event(event) => events.push(event),
+            AbiEntry::Enum(_) | AbiEntry::Other => {}
+        }
+    }
+
+    for event in events {
+        // Cairo 2 contracts emit one top-level `enum Event` whose variants just re-point at the
+        // actual per-event structs below; those structs are what we turn into `CairoEvent`s.
+        if event.kind == AbiEventKind::Enum {
+            continue;
+        }
+
+        let arguments = event
+            .members
+            .iter()
+            .flat_map(|member| resolve_member(member, &structs, 0))
+            .collect();
+        file_domain.add_cairo_event(CairoEvent::from_abi(short_name(&event.name), arguments));
+    }
+
+    Ok(file_domain)
+}
+
+fn resolve_member(
+    member: &AbiMember,
+    structs: &HashMap<&str, &AbiStruct>,
+    depth: usize,
+) -> Vec<CairoArgument> {
+    if let Some(postgres_type) = primitive_postgres_type(&member.r#type) {
+        return vec![CairoArgument::new(member.name.clone(), postgres_type)];
+    }
+
+    if depth >= MAX_RESOLUTION_DEPTH {
+        return vec![CairoArgument::new(member.name.clone(), PostgresType::String)];
+    }
+
+    if let Some(nested) = structs.get(member.r#type.as_str()) {
+        return nested
+            .members
+            .iter()
+            .flat_map(|m| resolve_member(m, structs, depth + 1))
+            .map(|arg| arg.nested_under(&member.name))
+            .collect();
+    }
+
+    // Enum-valued members (and anything else we don't recognize, e.g. a type the ABI doesn't
+    // declare a struct for) fall through to a generic string column rather than being flattened,
+    // since there's no single payload shape to expand.
+    vec![CairoArgument::new(member.name.clone(), PostgresType::String)]
+}
+
+fn primitive_postgres_type(type_name: &str) -> Option<PostgresType> {
+    match type_name {
+        "core::felt252" => Some(PostgresType::String),
+        "core::integer::u256" => Some(PostgresType::String),
+        "core::integer::u8" | "core::integer::u16" | "core::integer::u32" => {
+            Some(PostgresType::Int)
+        }
+        "core::integer::u64" | "core::integer::u128" => Some(PostgresType::String),
+        "core::bool" => Some(PostgresType::Bool),
+        "core::starknet::contract_address::ContractAddress" => Some(PostgresType::String),
+        _ => None,
+    }
+}
+
+/// ABI entries carry fully qualified names like `my_contract::Transfer`; we only want the last
+/// segment, same as `FileDomain::new` does for file stems.
+fn short_name(fully_qualified: &str) -> String {
+    fully_qualified
+        .rsplit("::")
+        .next()
+        .unwrap_or(fully_qualified)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{resolve_member, AbiMember, AbiStruct, HashMap, MAX_RESOLUTION_DEPTH};
+
+    fn member(name: &str, r#type: &str) -> AbiMember {
+        AbiMember {
+            name: name.to_string(),
+            r#type: r#type.to_string(),
+        }
+    }
+
+    #[test]
+    fn resolve_member_flat_primitive() {
+        let structs: HashMap<&str, &AbiStruct> = HashMap::new();
+
+        let args = resolve_member(&member("value", "core::integer::u256"), &structs, 0);
+
+        assert_eq!(args.len(), 1);
+        assert_eq!((&args[0]).to_string(), "value: String,");
+    }
+
+    #[test]
+    fn resolve_member_flattens_nested_struct() {
+        let transfer = AbiStruct {
+            name: "my_contract::Transfer".to_string(),
+            members: vec![
+                member("from", "core::starknet::contract_address::ContractAddress"),
+                member("to", "core::starknet::contract_address::ContractAddress"),
+            ],
+        };
+        let mut structs: HashMap<&str, &AbiStruct> = HashMap::new();
+        structs.insert(transfer.name.as_str(), &transfer);
+
+        let args = resolve_member(&member("transfer", "my_contract::Transfer"), &structs, 0);
+
+        let rendered: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        assert_eq!(
+            rendered,
+            vec!["transfer_from: String,".to_string(), "transfer_to: String,".to_string()]
+        );
+    }
+
+    #[test]
+    fn resolve_member_stops_at_depth_limit_on_cycle() {
+        // A struct referencing itself would recurse forever without the `MAX_RESOLUTION_DEPTH`
+        // guard; this pins that the recursion bottoms out instead of blowing the stack.
+        let node = AbiStruct {
+            name: "my_contract::Node".to_string(),
+            members: vec![member("next", "my_contract::Node")],
+        };
+        let mut structs: HashMap<&str, &AbiStruct> = HashMap::new();
+        structs.insert(node.name.as_str(), &node);
+
+        let args = resolve_member(&member("root", "my_contract::Node"), &structs, 0);
+
+        let mut expected_name = String::from("root");
+        for _ in 0..MAX_RESOLUTION_DEPTH {
+            expected_name.push_str("_next");
+        }
+
+        assert_eq!(args.len(), 1);
+        assert_eq!((&args[0]).to_string(), format!("{}: String,", expected_name));
+    }
+
+    #[test]
+    fn parse_abi_file_end_to_end() {
+        use super::parse_abi_file;
+
+        let path = std::env::temp_dir().join(format!(
+            "amenhotep_test_abi_{}.json",
+            std::process::id()
+        ));
+        std::fs::write(
+            &path,
+            r#"[
+                {
+                    "type": "event",
+                    "name": "my_contract::Transfer",
+                    "kind": "struct",
+                    "members": [
+                        {"name": "from", "type": "core::starknet::contract_address::ContractAddress"},
+                        {"name": "to", "type": "core::starknet::contract_address::ContractAddress"},
+                        {"name": "value", "type": "core::integer::u256"}
+                    ]
+                }
+            ]"#,
+        )
+        .unwrap();
+
+        let result = parse_abi_file(&path);
+        std::fs::remove_file(&path).ok();
+        let domain = result.unwrap();
+
+        assert_eq!(domain.events.len(), 1);
+        assert_eq!(domain.events[0].name(), "Transfer");
+    }
+}
+
@@ -1,7 +1,90 @@
 use serde::Serialize;
+use std::collections::HashMap;
 use thiserror::Error;
 
-use crate::parser::{CairoEvent, FileDomain};
+use crate::parser::{capitalize, CairoEvent, CairoType, FileDomain, Identifier};
+
+/// Handler names resolved per domain, positionally parallel to the `file_domains` slice passed
+/// to `compute_handler_aliases` (not keyed by `FileDomain::name`, since two contracts in
+/// different directories can share a filename and therefore a name).
+type HandlerAliases = Vec<Vec<String>>;
+
+/// Collects every event's `handle*` name across all sources and, for any name used by more than
+/// one event, rewrites the conflicting occurrences to `handleTransfer1`, `handleTransfer2`, ...
+/// while the first occurrence keeps the un-suffixed name. Domains are visited in name order so
+/// the aliasing is stable across runs regardless of the order `file_domains` was built in, but
+/// the result is indexed by each domain's original position in `file_domains`.
+pub(crate) fn compute_handler_aliases(file_domains: &[FileDomain]) -> HandlerAliases {
+    let mut order: Vec<usize> = (0..file_domains.len()).collect();
+    order.sort_by(|&a, &b| file_domains[a].name.cmp(&file_domains[b].name));
+
+    let mut occurrences: HashMap<&str, usize> = HashMap::new();
+    for domain in file_domains {
+        for event in &domain.events {
+            *occurrences.entry(event.name()).or_insert(0) += 1;
+        }
+    }
+
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    let mut aliases: HandlerAliases = vec![Vec::new(); file_domains.len()];
+    for index in order {
+        let domain = &file_domains[index];
+        let domain_aliases = domain
+            .events
+            .iter()
+            .map(|event| {
+                let base = event.to_js_function_string();
+                if occurrences[event.name()] <= 1 {
+                    return base;
+                }
+
+                let seen_count = seen.entry(event.name()).or_insert(0);
+                let alias = match *seen_count {
+                    0 => base,
+                    n => format!("{}{}", base, n),
+                };
+                *seen_count += 1;
+                alias
+            })
+            .collect();
+        aliases[index] = domain_aliases;
+    }
+
+    aliases
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compute_handler_aliases;
+    use crate::parser::FileDomain;
+
+    #[test]
+    fn aliases_same_name_domains_by_position_not_by_name() {
+        // `a/token.cairo` and `b/token.cairo` both collapse to the domain name `Token`, so the
+        // alias map must be threaded positionally rather than keyed by that shared name.
+        let domain_a = FileDomain::new_for_test("Token", vec!["Transfer"]);
+        let domain_b = FileDomain::new_for_test("Token", vec!["Approval"]);
+        let domains = vec![domain_a, domain_b];
+
+        let aliases = compute_handler_aliases(&domains);
+
+        assert_eq!(aliases.len(), 2);
+        assert_eq!(aliases[0], vec!["handleTransfer".to_string()]);
+        assert_eq!(aliases[1], vec!["handleApproval".to_string()]);
+    }
+
+    #[test]
+    fn aliases_colliding_event_names_across_domains() {
+        let domain_a = FileDomain::new_for_test("AToken", vec!["Transfer"]);
+        let domain_b = FileDomain::new_for_test("BToken", vec!["Transfer"]);
+        let domains = vec![domain_a, domain_b];
+
+        let aliases = compute_handler_aliases(&domains);
+
+        assert_eq!(aliases[0], vec!["handleTransfer".to_string()]);
+        assert_eq!(aliases[1], vec!["handleTransfer1".to_string()]);
+    }
+}
 
 #[derive(Serialize)]
 pub(crate) struct CheckpointConfiguration {
@@ -9,12 +92,13 @@ pub(crate) struct CheckpointConfiguration {
     pub sources: Vec<CheckpointSource>,
 }
 
-impl From<&[FileDomain]> for CheckpointConfiguration {
-    fn from(value: &[FileDomain]) -> Self {
-        let sources = value
+impl CheckpointConfiguration {
+    fn new(domains: &[FileDomain], handler_aliases: &HandlerAliases) -> Self {
+        let sources = domains
             .iter()
-            .filter(|fd| !fd.events.is_empty())
-            .map(|fd| fd.into())
+            .enumerate()
+            .filter(|(_, fd)| !fd.events.is_empty())
+            .map(|(i, fd)| CheckpointSource::new(fd, &handler_aliases[i]))
             .collect::<Vec<CheckpointSource>>();
         Self {
             network_node_url: "<CHANGE_ME>".to_string(),
@@ -31,12 +115,13 @@ pub(crate) struct CheckpointSource {
     pub events: Vec<CheckpointEvent>,
 }
 
-impl From<&FileDomain> for CheckpointSource {
-    fn from(value: &FileDomain) -> Self {
-        let events = value
+impl CheckpointSource {
+    fn new(domain: &FileDomain, handler_aliases: &[String]) -> Self {
+        let events = domain
             .events
             .iter()
-            .map(|e| e.into())
+            .zip(handler_aliases)
+            .map(|(event, alias)| CheckpointEvent::new(event, alias))
             .collect::<Vec<CheckpointEvent>>();
         Self {
             contract: "<CHANGE_ME>".to_string(),
@@ -53,11 +138,11 @@ pub(crate) struct CheckpointEvent {
     #[serde(rename = "fn")]
     pub function: String,
 }
-impl From<&CairoEvent> for CheckpointEvent {
-    fn from(value: &CairoEvent) -> Self {
+impl CheckpointEvent {
+    fn new(value: &CairoEvent, handler_name: &str) -> Self {
         Self {
             name: value.to_js_function_name_string(),
-            function: value.to_js_function_string(),
+            function: handler_name.to_string(),
         }
     }
 }
@@ -69,23 +154,220 @@ pub struct GeneratedFile {
 }
 
 #[derive(Debug, Error)]
-pub(crate) enum GeneratorError {}
+pub(crate) enum GeneratorError {
+    #[error("unknown backend `{0}`, expected one of: checkpoint, apibara")]
+    UnknownBackend(String),
+}
+
+/// A Starknet indexing framework that can turn parsed [`FileDomain`]s into its own set of
+/// generated files. [`CheckpointBackend`] is the original `@snapshot-labs/checkpoint` target;
+/// new frameworks plug in by implementing this trait, much like ethers-rs' `Abigen` factors code
+/// generation behind a reusable context rather than one hardcoded emitter.
+pub(crate) trait Backend {
+    fn generate(&self, domains: &[FileDomain]) -> Result<Vec<GeneratedFile>, GeneratorError>;
+}
 
+/// Dispatches to the named backend. `backend_name` is expected to come straight from the CLI's
+/// `--backend` argument.
 pub(crate) fn generate_indexer(
+    backend_name: &str,
     file_domains: &[FileDomain],
 ) -> Result<Vec<GeneratedFile>, GeneratorError> {
-    let mut files = Vec::new();
-    for domain in file_domains {
-        if domain.events.is_empty() {
-            continue;
+    let backend: Box<dyn Backend> = match backend_name {
+        "checkpoint" => Box::new(CheckpointBackend),
+        "apibara" => Box::new(ApibaraBackend),
+        other => return Err(GeneratorError::UnknownBackend(other.to_string())),
+    };
+
+    backend.generate(file_domains)
+}
+
+/// Emits the `@snapshot-labs/checkpoint` GraphQL model, `configuration.json` and
+/// `CheckpointWriter`-typed JS handlers for each domain.
+pub(crate) struct CheckpointBackend;
+
+impl Backend for CheckpointBackend {
+    fn generate(&self, file_domains: &[FileDomain]) -> Result<Vec<GeneratedFile>, GeneratorError> {
+        let handler_aliases = compute_handler_aliases(file_domains);
+
+        let mut files = Vec::new();
+        for (i, domain) in file_domains.iter().enumerate() {
+            if domain.events.is_empty() {
+                continue;
+            }
+
+            files.push(generate_model(domain));
+            files.push(generate_data_writer(domain, &handler_aliases[i]));
         }
+        files.extend(
+            file_domains
+                .iter()
+                .filter_map(|domain| generate_storage_model(domain, "String!", "gql")),
+        );
+        files.push(generate_config(file_domains, &handler_aliases));
 
-        files.push(generate_model(&domain));
-        files.push(generate_data_writer(&domain));
+        Ok(files)
     }
-    files.push(generate_config(file_domains));
+}
+
+/// Emits an Apibara/Dojo-style target: one GraphQL schema plus one transform module per domain.
+pub(crate) struct ApibaraBackend;
+
+impl Backend for ApibaraBackend {
+    fn generate(&self, file_domains: &[FileDomain]) -> Result<Vec<GeneratedFile>, GeneratorError> {
+        let handler_aliases = compute_handler_aliases(file_domains);
+
+        let mut files = Vec::new();
+        for (i, domain) in file_domains.iter().enumerate() {
+            if domain.events.is_empty() {
+                continue;
+            }
+
+            files.push(generate_apibara_schema(domain));
+            files.push(generate_apibara_transform(domain, &handler_aliases[i]));
+        }
+        files.extend(
+            file_domains
+                .iter()
+                .filter_map(|domain| generate_storage_model(domain, "ID!", "graphql")),
+        );
 
-    Ok(files)
+        Ok(files)
+    }
+}
+
+/// Derives a persisted entity from a contract's `Storage` struct: scalar fields become plain
+/// columns, and each `LegacyMap<K, V>` becomes its own lookup/relation table keyed by `K` (a
+/// tuple key produces one column per component). Returns `None` when there's no parsed storage,
+/// or it parsed to no usable fields. `id_scalar` and `extension` let each backend keep its own
+/// `id` scalar and file-extension convention (Checkpoint's `String!`/`.gql` vs. Apibara's
+/// `ID!`/`.graphql`) rather than silently borrowing Checkpoint's.
+fn generate_storage_model(domain: &FileDomain, id_scalar: &str, extension: &str) -> Option<GeneratedFile> {
+    let storage = domain.storage.as_ref()?;
+    if storage.fields().is_empty() {
+        return None;
+    }
+
+    let mut columns = Vec::new();
+    let mut related_entities = Vec::new();
+
+    // `HashMap` iteration order is randomized per-process; sort by identifier so the emitted
+    // schema is byte-identical across runs (required for the `FileWriter` skip-if-unchanged
+    // check and for deterministic generated output generally).
+    let mut fields: Vec<(&Identifier, &CairoType)> = storage.fields().iter().collect();
+    fields.sort_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+
+    for (identifier, cairo_type) in fields {
+        match cairo_type {
+            CairoType::LegacyMap(key, value) => {
+                related_entities.push(generate_legacy_map_entity(
+                    &domain.name,
+                    identifier.as_str(),
+                    key,
+                    value,
+                    id_scalar,
+                ));
+            }
+            scalar => columns.push(format!("    {}: {}", identifier.as_str(), scalar.to_string())),
+        }
+    }
+
+    let mut content = format!(
+        "type {}Storage {{\n    id: {}\n{}\n}}\n",
+        domain.name,
+        id_scalar,
+        columns.join("\n")
+    );
+    for entity in related_entities {
+        content.push('\n');
+        content.push_str(&entity);
+    }
+
+    Some(GeneratedFile {
+        name: format!("{}Storage.{}", domain.name, extension),
+        content,
+    })
+}
+
+/// A `LegacyMap<K, V>` storage field becomes its own entity: a tuple `K` is spread across one
+/// `key0`, `key1`, ... column per component (a relation keyed by a composite key), while a scalar
+/// `K` becomes a single `key` column (a straightforward indexed lookup table).
+fn generate_legacy_map_entity(
+    domain_name: &str,
+    field_name: &str,
+    key: &CairoType,
+    value: &CairoType,
+    id_scalar: &str,
+) -> String {
+    let entity_name = format!("{}{}", domain_name, capitalize(field_name.trim_start_matches('_')));
+    let key_columns = match key {
+        CairoType::Tuple(components) => components
+            .iter()
+            .enumerate()
+            .map(|(i, component)| format!("    key{}: {}", i, component.to_string()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        scalar => format!("    key: {}", scalar.to_string()),
+    };
+
+    format!(
+        "type {} {{\n    id: {}\n{}\n    value: {}\n}}\n",
+        entity_name,
+        id_scalar,
+        key_columns,
+        value.to_string()
+    )
+}
+
+fn generate_apibara_schema(domain: &FileDomain) -> GeneratedFile {
+    let content = format!(
+        r#"
+type {} {{
+    id: ID!
+    {}
+}}
+"#,
+        domain.name,
+        events_to_graphql(&domain.events)
+    );
+    GeneratedFile {
+        name: format!("{}.graphql", domain.name),
+        content,
+    }
+}
+
+fn generate_apibara_transform(domain: &FileDomain, handler_aliases: &[String]) -> GeneratedFile {
+    let content = format!(
+        r#"
+export default function transform(block) {{
+    const rows = [];
+
+{}
+
+    return rows;
+}}
+"#,
+        events_to_apibara_handlers(&domain.events, handler_aliases),
+    );
+    GeneratedFile {
+        name: format!("{}Transform.js", domain.name),
+        content,
+    }
+}
+
+fn events_to_apibara_handlers(events: &[CairoEvent], handler_aliases: &[String]) -> String {
+    events
+        .iter()
+        .zip(handler_aliases)
+        .map(|(event, alias)| {
+            format!(
+                "    // {} -> {}\n    new Error('Not implemented yet !');",
+                event.to_js_function_name_string(),
+                alias
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 fn generate_model(domain: &FileDomain) -> GeneratedFile {
@@ -119,8 +401,8 @@ fn events_to_graphql(events: &[CairoEvent]) -> String {
         .join("\n")
 }
 
-fn generate_config(domain: &[FileDomain]) -> GeneratedFile {
-    let content: CheckpointConfiguration = domain.into();
+fn generate_config(domains: &[FileDomain], handler_aliases: &HandlerAliases) -> GeneratedFile {
+    let content = CheckpointConfiguration::new(domains, handler_aliases);
     let content_str = serde_json::to_string_pretty(&content).unwrap();
     GeneratedFile {
         name: "configuration.json".to_string(),
@@ -128,15 +410,15 @@ fn generate_config(domain: &[FileDomain]) -> GeneratedFile {
     }
 }
 
-fn generate_data_writer(domain: &FileDomain) -> GeneratedFile {
-    let content = generate_data_writer_content(&domain.events);
+fn generate_data_writer(domain: &FileDomain, handler_aliases: &[String]) -> GeneratedFile {
+    let content = generate_data_writer_content(&domain.events, handler_aliases);
     GeneratedFile {
         name: String::from(format!("{}DataWriter.js", &domain.name)),
         content,
     }
 }
 
-fn generate_data_writer_content(events: &[CairoEvent]) -> String {
+fn generate_data_writer_content(events: &[CairoEvent], handler_aliases: &[String]) -> String {
     format!(
         r#"
 import type {{ CheckpointWriter }} from '@snapshot-labs/checkpoint';
@@ -147,14 +429,15 @@ export async function handleDeploy() {{
 
 {}
         "#,
-        events_to_js_function(events),
+        events_to_js_function(events, handler_aliases),
     )
 }
 
-fn events_to_js_function(events: &[CairoEvent]) -> String {
+fn events_to_js_function(events: &[CairoEvent], handler_aliases: &[String]) -> String {
     events
         .iter()
-        .map(|e| e.to_js_function())
+        .zip(handler_aliases)
+        .map(|(e, alias)| e.to_js_function(alias))
         .collect::<Vec<_>>()
         .join("\n")
 }
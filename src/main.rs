@@ -1,13 +1,18 @@
 use anyhow::Result;
+use notify::{RecursiveMode, Watcher};
 use std::path::PathBuf;
+use std::sync::mpsc::channel;
+use std::time::Duration;
 use thiserror::Error;
 use writer::{ConsoleWriter, FileWriter, Writer};
 
 use crate::{
+    abi::parse_abi_file,
     generator::generate_indexer,
     parser::{files_to_parse, parse_cairo_file, FileDomain},
 };
 
+mod abi;
 mod generator;
 mod parser;
 mod writer;
@@ -23,15 +28,48 @@ fn main() -> Result<()> {
                     clap::arg!(<PATH> ... "The repository to parse out.")
                         .value_parser(clap::value_parser!(PathBuf)),
                 )
+                .arg(clap::arg!(--strict "Exit with a non-zero status if any parsing diagnostic was reported").required(false))
+                .arg(
+                    clap::arg!(--backend <NAME> "Target indexer backend [checkpoint, apibara]")
+                        .required(false)
+                        .default_value("checkpoint"),
+                )
                 .arg_required_else_help(true),
         )
         .subcommand(
             clap::Command::new("generate")
                 .about("Generate the files output")
+                .arg(
+                    clap::arg!([PATH] ... "The repository to parse out.")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .required_unless_present("from-abi"),
+                )
+                .arg(
+                    clap::arg!(--"from-abi" <ABI_PATH> "Generate from a compiled Starknet class ABI JSON file instead of walking .cairo sources")
+                        .required(false)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(clap::arg!(--strict "Exit with a non-zero status if any parsing diagnostic was reported").required(false))
+                .arg(
+                    clap::arg!(--backend <NAME> "Target indexer backend [checkpoint, apibara]")
+                        .required(false)
+                        .default_value("checkpoint"),
+                )
+                .arg_required_else_help(true),
+        )
+        .subcommand(
+            clap::Command::new("watch")
+                .about("Regenerate automatically whenever a .cairo file changes")
                 .arg(
                     clap::arg!(<PATH> ... "The repository to parse out.")
                         .value_parser(clap::value_parser!(PathBuf)),
                 )
+                .arg(clap::arg!(--strict "Exit with a non-zero status if any parsing diagnostic was reported").required(false))
+                .arg(
+                    clap::arg!(--backend <NAME> "Target indexer backend [checkpoint, apibara]")
+                        .required(false)
+                        .default_value("checkpoint"),
+                )
                 .arg_required_else_help(true),
         );
 
@@ -44,15 +82,31 @@ fn main() -> Result<()> {
                 .into_iter()
                 .flatten()
                 .collect::<Vec<_>>();
-            handle_generate_indexer(paths, ConsoleWriter {})?
+            let backend = matches.get_one::<String>("backend").unwrap();
+            handle_generate_indexer(paths, ConsoleWriter {}, matches.get_flag("strict"), backend)?
         }
         Some(("generate", matches)) => {
+            let backend = matches.get_one::<String>("backend").unwrap();
+            if let Some(abi_path) = matches.get_one::<PathBuf>("from-abi") {
+                handle_generate_indexer_from_abi(abi_path, FileWriter {}, backend)?
+            } else {
+                let paths = matches
+                    .get_many::<std::path::PathBuf>("PATH")
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>();
+                handle_generate_indexer(paths, FileWriter {}, matches.get_flag("strict"), backend)?
+            }
+        }
+        Some(("watch", matches)) => {
             let paths = matches
                 .get_many::<std::path::PathBuf>("PATH")
                 .into_iter()
                 .flatten()
+                .cloned()
                 .collect::<Vec<_>>();
-            handle_generate_indexer(paths, FileWriter {})?
+            let backend = matches.get_one::<String>("backend").unwrap();
+            handle_watch(paths, FileWriter {}, matches.get_flag("strict"), backend)?
         }
         _ => unreachable!("clap should ensure we don't get here"),
     }
@@ -60,7 +114,12 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-fn handle_generate_indexer(paths: Vec<&PathBuf>, writer: impl Writer) -> Result<()> {
+fn handle_generate_indexer(
+    paths: Vec<&PathBuf>,
+    writer: impl Writer,
+    strict: bool,
+    backend: &str,
+) -> Result<()> {
     let mut files = Vec::new();
     for path in paths {
         let mut to_parse = files_to_parse(path, files.clone())?;
@@ -70,12 +129,25 @@ fn handle_generate_indexer(paths: Vec<&PathBuf>, writer: impl Writer) -> Result<
     println!("Files to parse : {:#?}", files);
 
     let mut file_domains: Vec<FileDomain> = Vec::new();
+    let mut diagnostics = Vec::new();
     for file in files {
-        let events = parse_cairo_file(file)?;
-        file_domains.push(events);
+        let (domain, mut file_diagnostics) = parse_cairo_file(file)?;
+        file_domains.push(domain);
+        diagnostics.append(&mut file_diagnostics);
     }
 
-    let files = generate_indexer(&file_domains)?;
+    for diagnostic in &diagnostics {
+        eprintln!("{}", diagnostic);
+    }
+
+    if strict && !diagnostics.is_empty() {
+        anyhow::bail!(
+            "{} parsing diagnostic(s) reported, aborting due to --strict",
+            diagnostics.len()
+        );
+    }
+
+    let files = generate_indexer(backend, &file_domains)?;
 
     for file in files {
         writer.write(&file)?;
@@ -83,3 +155,61 @@ fn handle_generate_indexer(paths: Vec<&PathBuf>, writer: impl Writer) -> Result<
 
     Ok(())
 }
+
+fn handle_generate_indexer_from_abi(
+    abi_path: &PathBuf,
+    writer: impl Writer,
+    backend: &str,
+) -> Result<()> {
+    let file_domain = parse_abi_file(abi_path)?;
+
+    let files = generate_indexer(backend, &[file_domain])?;
+
+    for file in files {
+        writer.write(&file)?;
+    }
+
+    Ok(())
+}
+
+/// Performs an initial generation, then watches `paths` and regenerates whenever a `.cairo` file
+/// is created, modified or removed underneath them. A burst of events from a single editor save
+/// (write, then rename, then touch the parent directory) is drained before regenerating so that
+/// save triggers exactly one rebuild.
+fn handle_watch(paths: Vec<PathBuf>, writer: impl Writer + Copy, strict: bool, backend: &str) -> Result<()> {
+    handle_generate_indexer(paths.iter().collect(), writer, strict, backend)?;
+
+    let (tx, rx) = channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    for path in &paths {
+        watcher.watch(path, RecursiveMode::Recursive)?;
+    }
+
+    println!("Watching {:#?} for .cairo changes...", paths);
+
+    while let Ok(first_event) = rx.recv() {
+        // Debounce: drain whatever else arrives within the same burst before acting, but check
+        // every drained event too. An atomic save (write a temp file, then rename onto the real
+        // `.cairo` path) emits a non-`.cairo` event before the one that actually touches it, so
+        // only checking `first_event` would miss the real change.
+        let mut touched = touches_cairo_file(&first_event);
+        while let Ok(event) = rx.recv_timeout(Duration::from_millis(200)) {
+            touched = touched || touches_cairo_file(&event);
+        }
+
+        if !touched {
+            continue;
+        }
+
+        println!("Change detected, regenerating...");
+        if let Err(err) = handle_generate_indexer(paths.iter().collect(), writer, strict, backend) {
+            eprintln!("regeneration failed: {err}");
+        }
+    }
+
+    Ok(())
+}
+
+fn touches_cairo_file(event: &notify::Result<notify::Event>) -> bool {
+    matches!(event, Ok(event) if event.paths.iter().any(|p| p.extension().is_some_and(|ext| ext == "cairo")))
+}
@@ -1,6 +1,7 @@
 use anyhow::Result;
 use regex::Regex;
 use std::collections::HashMap;
+use std::fmt;
 use std::hash;
 use std::io::BufRead;
 use std::str::Split;
@@ -11,10 +12,36 @@ use std::{
 };
 use thiserror::Error;
 
+/// A position in a parsed `.cairo` file, used to point diagnostics at the offending line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum ParserError {
     #[error("file must have a .cairo extension")]
     InvalidFileExtension,
+    #[error("{file}:{location}: unknown type `{found}` (expected one of {expected:?})")]
+    UnknownType {
+        file: String,
+        location: Location,
+        found: String,
+        expected: Vec<&'static str>,
+    },
+    #[error("{file}:{location}: malformed event declaration `{line}`")]
+    MalformedEvent {
+        file: String,
+        location: Location,
+        line: String,
+    },
 }
 
 pub(crate) fn files_to_parse<P: AsRef<Path> + std::convert::AsRef<std::ffi::OsStr>>(
@@ -40,8 +67,16 @@ pub(crate) fn files_to_parse<P: AsRef<Path> + std::convert::AsRef<std::ffi::OsSt
 
     Ok(files)
 }
-#[derive(PartialEq, PartialOrd)]
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Clone)]
 pub struct Identifier(String);
+
+impl Identifier {
+    pub(crate) fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum CairoType {
     Felt252,
     ContractAddress,
@@ -52,110 +87,174 @@ pub enum CairoType {
     U128,
     U256,
     LegacyMap(Box<CairoType>, Box<CairoType>),
-    //    Tuple(Vec<CairoType>),
+    Tuple(Vec<CairoType>),
 }
 
 impl ToString for CairoType {
     fn to_string(&self) -> String {
         match self {
-            Felt252 => String::from("String!"),
-            ContractAddress => String::from("String!"),
-            U8 => String::from("Int!"),
-            U16 => String::from("Int!"),
-            U32 => String::from("Int!"),
-            U64 => String::from("String!"),
-            U128 => String::from("String!"),
-            U256 => String::from("String!"),
-            LegacyMap(key, value) => {
+            CairoType::Felt252 => String::from("String!"),
+            CairoType::ContractAddress => String::from("String!"),
+            CairoType::U8 => String::from("Int!"),
+            CairoType::U16 => String::from("Int!"),
+            CairoType::U32 => String::from("Int!"),
+            CairoType::U64 => String::from("String!"),
+            CairoType::U128 => String::from("String!"),
+            CairoType::U256 => String::from("String!"),
+            CairoType::LegacyMap(key, value) => {
                 format!("Map!({}, {})", key.to_string(), value.to_string())
             }
+            CairoType::Tuple(components) => format!(
+                "({})",
+                components
+                    .iter()
+                    .map(|c| c.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
         }
     }
 }
 
+#[derive(Debug)]
 pub struct CairoStorage {
     fields: HashMap<Identifier, CairoType>,
 }
 
-impl From<Vec<String>> for CairoStorage {
-    fn from(value: Vec<String>) -> Self {
-        let fields_vec: Vec<(Identifier, CairoType)> = value
+impl CairoStorage {
+    pub(crate) fn fields(&self) -> &HashMap<Identifier, CairoType> {
+        &self.fields
+    }
+
+    /// Parses the body of `struct Storage { ... }`, reporting an `UnknownType` diagnostic per
+    /// unrecognized field instead of panicking. `lines` pairs each raw field with the source
+    /// line it came from so diagnostics can point back at it.
+    fn from_lines(lines: &[(usize, String)], file: &str) -> (Self, Vec<ParserError>) {
+        let mut diagnostics = Vec::new();
+        let fields: HashMap<Identifier, CairoType> = lines
             .iter()
-            .map(|v| v.trim().split(":").into_iter().take(2).collect())
-            .map(|v: Vec<&str>| {
-                let key = v[0].trim();
-                let value = v[1].trim();
-                let key = Identifier(key.to_string());
-                let value = CairoType::from(value.to_string());
-                (key, value)
+            .filter_map(|(line_nr, raw)| {
+                let mut parts = raw.trim().splitn(2, ':');
+                let key = parts.next()?.trim();
+                let value = parts.next()?.trim().trim_end_matches(',');
+                if key.is_empty() || value.is_empty() {
+                    return None;
+                }
+
+                match CairoType::parse(value) {
+                    Ok(cairo_type) => Some((Identifier(key.to_string()), cairo_type)),
+                    Err(found) => {
+                        diagnostics.push(ParserError::UnknownType {
+                            file: file.to_string(),
+                            location: Location {
+                                line: *line_nr,
+                                column: 1,
+                            },
+                            found,
+                            expected: CairoType::KNOWN_TYPES.to_vec(),
+                        });
+                        None
+                    }
+                }
             })
             .collect();
-        let mut fields = HashMap::new();
-        fields_vec.iter().for_each(|(k, v)| {
-            println!("{}: {}", &k.0, &v.to_string());
-            fields.insert(k, v);
-        });
-        Self {
-            fields: HashMap::new(),
-        }
+
+        (Self { fields }, diagnostics)
     }
 }
 
-impl From<String> for CairoType {
-    fn from(value: String) -> Self {
+impl CairoType {
+    const KNOWN_TYPES: &'static [&'static str] = &[
+        "felt252",
+        "ContractAddress",
+        "u8",
+        "u16",
+        "u32",
+        "u64",
+        "u128",
+        "u256",
+        "LegacyMap<K, V>",
+        "(K, ...)",
+    ];
+
+    /// Parses a Cairo type name, returning the offending token on failure instead of panicking.
+    /// Handles `LegacyMap<K, V>` (including a tuple-keyed `K`, e.g.
+    /// `LegacyMap<(ContractAddress, ContractAddress), u256>`) by splitting on the balanced
+    /// `<...>`/`(...)` rather than naively splitting on the literal word `LegacyMap`, which
+    /// breaks as soon as the key or value itself contains a comma.
+    fn parse(value: &str) -> Result<Self, String> {
         let value = value.trim();
-        if value == "felt252" {
-            return CairoType::Felt252;
-        }
-
-        if value == "ContractAddress" {
-            return CairoType::ContractAddress;
-        }
-
-        if value == "u8" {
-            return CairoType::U8;
-        }
 
-        if value == "u16" {
-            return CairoType::U16;
-        }
-
-        if value == "u32" {
-            return CairoType::U32;
+        match value {
+            "felt252" => return Ok(CairoType::Felt252),
+            "ContractAddress" => return Ok(CairoType::ContractAddress),
+            "u8" => return Ok(CairoType::U8),
+            "u16" => return Ok(CairoType::U16),
+            "u32" => return Ok(CairoType::U32),
+            "u64" => return Ok(CairoType::U64),
+            "u128" => return Ok(CairoType::U128),
+            "u256" => return Ok(CairoType::U256),
+            _ => {}
         }
 
-        if value == "u64" {
-            return CairoType::U64;
+        if let Some(rest) = value.strip_prefix("LegacyMap") {
+            let inner = rest
+                .trim()
+                .strip_prefix('<')
+                .and_then(|s| s.strip_suffix('>'))
+                .ok_or_else(|| value.to_string())?;
+            let parts = split_top_level(inner, ',');
+            let [key, map_value] = parts.as_slice() else {
+                return Err(value.to_string());
+            };
+            return Ok(CairoType::LegacyMap(
+                Box::new(CairoType::parse(key)?),
+                Box::new(CairoType::parse(map_value)?),
+            ));
         }
 
-        if value == "u128" {
-            return CairoType::U128;
+        if let Some(inner) = value.strip_prefix('(').and_then(|s| s.strip_suffix(')')) {
+            let components = split_top_level(inner, ',')
+                .into_iter()
+                .map(CairoType::parse)
+                .collect::<Result<Vec<_>, _>>()?;
+            return Ok(CairoType::Tuple(components));
         }
 
-        if value == "u256" {
-            return CairoType::U256;
-        }
+        Err(value.to_string())
+    }
+}
 
-        if value.starts_with("LegacyMap") {
-            let mut value = value.split("LegacyMap");
-            let mut key = value.next().unwrap().trim();
-            let mut value = value.next().unwrap().trim();
-            key = key.trim_start_matches('<').trim_end_matches('>');
-            value = value.trim_start_matches('<').trim_end_matches('>');
-            return CairoType::LegacyMap(
-                Box::new(CairoType::from(key.to_string())),
-                Box::new(CairoType::from(value.to_string())),
-            );
+/// Splits `input` on `delimiter` at depth zero, treating `(`/`)` and `<`/`>` as nesting so a
+/// tuple key's own commas (or a nested generic's) don't get mistaken for the outer split point.
+fn split_top_level(input: &str, delimiter: char) -> Vec<&str> {
+    let mut depth = 0i32;
+    let mut start = 0;
+    let mut parts = Vec::new();
+    for (idx, ch) in input.char_indices() {
+        match ch {
+            '(' | '<' => depth += 1,
+            ')' | '>' => depth -= 1,
+            c if c == delimiter && depth == 0 => {
+                parts.push(input[start..idx].trim());
+                start = idx + c.len_utf8();
+            }
+            _ => {}
         }
-        panic!("Unknown type: {}", value);
     }
+    parts.push(input[start..].trim());
+    parts
 }
 
 pub(crate) fn parse_cairo_file<P: AsRef<Path> + std::convert::AsRef<std::ffi::OsStr>>(
     file: P,
-) -> Result<FileDomain> {
+) -> Result<(FileDomain, Vec<ParserError>)> {
+    let file_name = <P as AsRef<Path>>::as_ref(&file)
+        .to_string_lossy()
+        .to_string();
     let mut file_domain = FileDomain::new(&file);
-    let mut storage_buf: Vec<String> = Vec::new();
+    let mut diagnostics: Vec<ParserError> = Vec::new();
+    let mut storage_buf: Vec<(usize, String)> = Vec::new();
     let mut storage: Option<CairoStorage> = None;
     if let Ok(mut lines) = read_lines(file) {
         let mut line_nr = 0;
@@ -165,16 +264,17 @@ pub(crate) fn parse_cairo_file<P: AsRef<Path> + std::convert::AsRef<std::ffi::Os
 
                 if l.contains("struct Storage") {
                     while storage.is_none() {
-                        if let Some(l) = lines.next() {
-                            if let Ok(l) = l {
-                                line_nr += 1;
-
-                                if l.contains('}') {
-                                    storage = Some(storage_buf.clone().into());
-                                }
-
-                                storage_buf.push(l.clone());
+                        if let Some(Ok(l)) = lines.next() {
+                            line_nr += 1;
+
+                            if l.contains('}') {
+                                let (parsed_storage, mut storage_diagnostics) =
+                                    CairoStorage::from_lines(&storage_buf, &file_name);
+                                storage = Some(parsed_storage);
+                                diagnostics.append(&mut storage_diagnostics);
                             }
+
+                            storage_buf.push((line_nr, l.clone()));
                         }
                     }
                 }
@@ -184,24 +284,34 @@ pub(crate) fn parse_cairo_file<P: AsRef<Path> + std::convert::AsRef<std::ffi::Os
                     if let Some(Ok(event_line)) = lines.next() {
                         // increment there too as going to the next line
                         line_nr += 1;
-                        let mut cairo_event: CairoEvent = event_line.into();
-                        // not parsing emitted_at yet cause code comprehension is not impl yet.
-                        // this might be enough for poc
-                        cairo_event.definined_at(line_nr);
-                        file_domain.add_cairo_event(cairo_event);
+                        let location = Location {
+                            line: line_nr,
+                            column: 1,
+                        };
+                        match CairoEvent::try_from_line(&event_line, &file_name, location) {
+                            Ok(mut cairo_event) => {
+                                // not parsing emitted_at yet cause code comprehension is not impl yet.
+                                // this might be enough for poc
+                                cairo_event.definined_at(line_nr);
+                                file_domain.add_cairo_event(cairo_event);
+                            }
+                            Err(err) => diagnostics.push(err),
+                        }
                     }
                 }
             }
         }
     }
+    file_domain.storage = storage;
 
-    Ok(file_domain)
+    Ok((file_domain, diagnostics))
 }
 
 #[derive(Debug)]
 pub(crate) struct FileDomain {
     pub(crate) name: String,
     pub(crate) events: Vec<CairoEvent>,
+    pub(crate) storage: Option<CairoStorage>,
 }
 impl FileDomain {
     fn new<P: AsRef<Path> + std::convert::AsRef<std::ffi::OsStr>>(file: P) -> Self {
@@ -220,14 +330,53 @@ impl FileDomain {
         Self {
             name,
             events: vec![],
+            storage: None,
+        }
+    }
+
+    /// Same as [`FileDomain::new`] but for an ABI JSON file, whose stem becomes the domain name.
+    pub(crate) fn new_from_abi_path<P: AsRef<Path> + std::convert::AsRef<std::ffi::OsStr>>(
+        file: P,
+    ) -> Self {
+        let path: String = <P as AsRef<Path>>::as_ref(&file)
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let name = path
+            .split("/")
+            .last()
+            .unwrap()
+            .to_string()
+            .replace(".json", "");
+        let name = capitalize(&name);
+        Self {
+            name,
+            events: vec![],
+            storage: None,
         }
     }
-    fn add_cairo_event(&mut self, event: CairoEvent) {
+
+    pub(crate) fn add_cairo_event(&mut self, event: CairoEvent) {
         self.events.push(event);
     }
+
+    /// Builds a domain with the given name and bare event names, skipping file I/O entirely.
+    /// Only exists to give other modules' tests (e.g. `generator::tests`) a cheap `FileDomain`.
+    #[cfg(test)]
+    pub(crate) fn new_for_test(name: &str, event_names: Vec<&str>) -> Self {
+        Self {
+            name: name.to_string(),
+            events: event_names
+                .into_iter()
+                .map(|n| CairoEvent::from_abi(n.to_string(), vec![]))
+                .collect(),
+            storage: None,
+        }
+    }
 }
 
-fn capitalize(s: &str) -> String {
+pub(crate) fn capitalize(s: &str) -> String {
     let mut c = s.chars();
     match c.next() {
         None => String::new(),
@@ -243,10 +392,24 @@ pub struct CairoEvent {
     emitted_at: Vec<usize>,
 }
 impl CairoEvent {
+    /// Builds an event straight from resolved ABI data rather than from a parsed source line,
+    /// so there is no `line_nr`/`emitted_at` to carry over.
+    pub(crate) fn from_abi(name: String, arguments: Vec<CairoArgument>) -> Self {
+        Self {
+            name,
+            arguments,
+            ..Default::default()
+        }
+    }
+
     pub fn definined_at(&mut self, line: usize) {
         self.definition_at = line;
     }
 
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
     pub fn to_js_function_string(&self) -> String {
         format!("handle{}", self.name)
     }
@@ -255,16 +418,19 @@ impl CairoEvent {
         format!("new_{}", self.name.to_lowercase())
     }
 
-    pub fn to_js_function(&self) -> String {
+    /// Renders the handler body under `handler_name`, which callers resolve through
+    /// `generator::compute_handler_aliases` rather than always using `to_js_function_string`, so
+    /// that two contracts defining the same event don't collide on `handleTransfer`.
+    pub fn to_js_function(&self, handler_name: &str) -> String {
         format!(
             r#"
-export async function handle{}({{ block, tx, event, mysql }}: Parameters<CheckpointWriter>[0]) {{
+export async function {}({{ block, tx, event, mysql }}: Parameters<CheckpointWriter>[0]) {{
     if (!event) return;
 
     new Error('Not implemented yet !');
 }}
             "#,
-            self.name
+            handler_name
         )
     }
 }
@@ -304,6 +470,17 @@ pub struct CairoArgument {
 }
 
 impl CairoArgument {
+    pub(crate) fn new(name: String, r#type: PostgresType) -> Self {
+        Self { name, r#type }
+    }
+
+    /// Prefixes this argument's name with the name of the struct member it was flattened out of,
+    /// e.g. `from` nested under `transfer` becomes `transfer_from`.
+    pub(crate) fn nested_under(mut self, parent: &str) -> Self {
+        self.name = format!("{}_{}", parent, self.name);
+        self
+    }
+
     pub fn js_function_name(&self) -> String {
         format!("handle{}", self.name)
     }
@@ -325,24 +502,38 @@ impl ToString for &CairoArgument {
     }
 }
 
-impl From<String> for CairoEvent {
-    fn from(value: String) -> Self {
+impl CairoEvent {
+    /// Parses a `fn name(args)` event-declaration line, reporting a `MalformedEvent` diagnostic
+    /// instead of panicking when the line doesn't match the expected shape.
+    fn try_from_line(value: &str, file: &str, location: Location) -> Result<Self, ParserError> {
         let expr = Regex::new(r"(fn )(?<fn_name>[a-zA-Z]+)\((?<args>.*)\)").unwrap();
-        let captures = expr.captures(&value).unwrap();
-        let name = captures.name("fn_name").unwrap().as_str();
+        let captures = expr.captures(value).ok_or_else(|| ParserError::MalformedEvent {
+            file: file.to_string(),
+            location,
+            line: value.to_string(),
+        })?;
+        let name = captures
+            .name("fn_name")
+            .ok_or_else(|| ParserError::MalformedEvent {
+                file: file.to_string(),
+                location,
+                line: value.to_string(),
+            })?
+            .as_str();
         let args = captures
             .name("args")
-            .unwrap()
-            .as_str()
+            .map(|m| m.as_str())
+            .unwrap_or_default()
             .split(", ")
+            .filter(|s| !s.is_empty())
             .map(|s| s.into())
             .collect::<Vec<CairoArgument>>();
 
-        Self {
+        Ok(Self {
             name: name.to_string(),
             arguments: args,
             ..Default::default()
-        }
+        })
     }
 }
 
@@ -378,7 +569,7 @@ fn is_cairo_file<P: AsRef<Path> + std::convert::AsRef<std::ffi::OsStr>>(file: P)
 
 #[cfg(test)]
 mod tests {
-    use super::CairoStorage;
+    use super::{parse_cairo_file, CairoStorage, CairoType, Identifier, ParserError};
 
     #[test]
     fn test_parse_cairo_type() {
@@ -395,6 +586,88 @@ mod tests {
             "        _intrications: LegacyMap<ContractAddress, u64>,".to_owned(),
         ];
 
-        let cairo_type: CairoStorage = types.to_vec().into();
+        let lines: Vec<(usize, String)> = types
+            .iter()
+            .enumerate()
+            .map(|(i, l)| (i + 1, l.clone()))
+            .collect();
+
+        let (storage, diagnostics) = CairoStorage::from_lines(&lines, "test.cairo");
+        assert!(
+            diagnostics.is_empty(),
+            "unexpected diagnostics: {:?}",
+            diagnostics
+        );
+
+        let fields = storage.fields();
+        assert!(matches!(
+            fields.get(&Identifier("_name".to_string())),
+            Some(CairoType::Felt252)
+        ));
+        assert!(matches!(
+            fields.get(&Identifier("_initial_supply".to_string())),
+            Some(CairoType::U256)
+        ));
+
+        match fields.get(&Identifier("_balances".to_string())) {
+            Some(CairoType::LegacyMap(key, value)) => {
+                assert!(matches!(**key, CairoType::ContractAddress));
+                assert!(matches!(**value, CairoType::U256));
+            }
+            other => panic!("expected a LegacyMap for _balances, got {:?}", other),
+        }
+
+        match fields.get(&Identifier("_allowances".to_string())) {
+            Some(CairoType::LegacyMap(key, value)) => {
+                match &**key {
+                    CairoType::Tuple(components) => {
+                        assert_eq!(components.len(), 2);
+                        assert!(components
+                            .iter()
+                            .all(|c| matches!(c, CairoType::ContractAddress)));
+                    }
+                    other => panic!("expected a tuple key for _allowances, got {:?}", other),
+                }
+                assert!(matches!(**value, CairoType::U256));
+            }
+            other => panic!("expected a LegacyMap for _allowances, got {:?}", other),
+        }
+
+        match fields.get(&Identifier("_intrications".to_string())) {
+            Some(CairoType::LegacyMap(key, value)) => {
+                assert!(matches!(**key, CairoType::ContractAddress));
+                assert!(matches!(**value, CairoType::U64));
+            }
+            other => panic!("expected a LegacyMap for _intrications, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_cairo_file_accumulates_diagnostics() {
+        use std::io::Write;
+
+        let path = std::env::temp_dir().join(format!(
+            "amenhotep_test_diagnostics_{}.cairo",
+            std::process::id()
+        ));
+        {
+            let mut file = std::fs::File::create(&path).unwrap();
+            writeln!(file, "struct Storage {{").unwrap();
+            writeln!(file, "    _bogus: NotAType,").unwrap();
+            writeln!(file, "}}").unwrap();
+            writeln!(file, "#[event]").unwrap();
+            writeln!(file, "not a valid event line").unwrap();
+        }
+
+        let result = parse_cairo_file(&path);
+        std::fs::remove_file(&path).ok();
+        let (domain, diagnostics) = result.unwrap();
+
+        // Both the bad storage field and the malformed event line should be reported, not just
+        // the first one encountered, and the run should finish rather than bail out early.
+        assert_eq!(diagnostics.len(), 2);
+        assert!(matches!(diagnostics[0], ParserError::UnknownType { .. }));
+        assert!(matches!(diagnostics[1], ParserError::MalformedEvent { .. }));
+        assert!(domain.events.is_empty());
     }
 }
@@ -15,10 +15,20 @@ pub trait Writer {
     fn write(&self, file: &GeneratedFile) -> Result<(), WriterError>;
 }
 
+#[derive(Clone, Copy)]
 pub struct FileWriter {}
 
 impl Writer for FileWriter {
     fn write(&self, file: &GeneratedFile) -> Result<(), WriterError> {
+        // Skip the write entirely when the file is already up to date so that `watch` mode
+        // doesn't churn the filesystem (and whatever is tailing these files, e.g. a dev server)
+        // on every regeneration.
+        if let Ok(existing) = std::fs::read_to_string(&file.name) {
+            if existing == file.content {
+                return Ok(());
+            }
+        }
+
         let mut fs_file = File::create(&file.name).map_err(|_| WriterError::FailedToCreateFile)?;
         fs_file
             .write_all(file.content.as_bytes())
@@ -27,6 +37,7 @@ impl Writer for FileWriter {
     }
 }
 
+#[derive(Clone, Copy)]
 pub struct ConsoleWriter {}
 
 impl Writer for ConsoleWriter {